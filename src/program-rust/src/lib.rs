@@ -1,21 +1,108 @@
 use borsh::{BorshDeserialize, BorshSerialize}; //importing libraries
+use num_derive::FromPrimitive;
 use solana_program::{
     //below is just an indicator of all libraries we need access to in order to make
     //our calls into the Solana runtime
     account_info::{next_account_info, AccountInfo},
+    decode_error::DecodeError,
     entrypoint,
     entrypoint::ProgramResult,
     msg,
-    program_error::ProgramError,
+    program::{invoke, invoke_signed},
+    program_error::{PrintProgramError, ProgramError},
     pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
 };
+use std::mem::size_of;
+use thiserror::Error;
 
 /// Define the type of state stored in accounts
 #[derive(BorshSerialize, BorshDeserialize, Debug)] //type of macro in the form of an annotation (similar to Javascript annotation)
 pub struct GreetingAccount {
     /// number of greetings
     pub counter: u32, //not holding any real worth while data right now
-    //pub txt: String --> David Choi
+    /// an arbitrary message attached to the account; growing this can require reallocation
+    pub message: String,
+}
+
+/// The account data length needed to hold a `GreetingAccount` whose message is
+/// `message_len` bytes: the discriminator, the counter, and Borsh's 4-byte string
+/// length prefix plus the message bytes themselves.
+fn greeting_account_len(message_len: usize) -> usize {
+    8 + size_of::<u32>() + size_of::<u32>() + message_len
+}
+
+/// First 8 bytes of sha256("account:GreetingAccount"), written at the front of the
+/// account's data as a fixed tag. Checking it before trusting the rest of the bytes
+/// guards against accidentally being handed an account of the wrong layout.
+pub const GREETING_ACCOUNT_DISCRIMINATOR: [u8; 8] = [190, 16, 56, 57, 246, 26, 112, 24];
+
+/// Instructions that clients can send to drive the program, encoded with Borsh
+/// so the byte layout matches whatever SDK packs them on the client side.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub enum GreetingInstruction {
+    /// Increment the counter by the given amount.
+    Increment(u32),
+    /// Reset the counter back to zero.
+    Reset,
+    /// Set the counter to an explicit value.
+    SetCounter(u32),
+    /// Provision the greeting account at the PDA derived from the payer and `seed`,
+    /// so callers don't need to pre-create and fund the account themselves.
+    Initialize { seed: String },
+    /// Replace the account's message, growing the account (and paying any extra
+    /// rent) if the new message no longer fits in the existing data length.
+    /// The payer and the system program follow the greeting account in the account
+    /// list; the handler reads them only when growth is needed, so a message that
+    /// fits needs just the greeting account, while a growing call sent without them
+    /// fails with a generic `NotEnoughAccountKeys`.
+    SetMessage(String),
+}
+
+/// Errors the program can return, each mapped to a distinct `ProgramError::Custom`
+/// code so `solana logs` shows a readable message instead of a generic variant.
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum GreetingError {
+    #[error("Account is not owned by this program")]
+    WrongOwner,
+    #[error("Account is not writable")]
+    NotWritable,
+    #[error("Counter overflowed")]
+    CounterOverflow,
+    #[error("Account is not rent exempt")]
+    NotRentExempt,
+}
+
+impl From<GreetingError> for ProgramError {
+    fn from(e: GreetingError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for GreetingError {
+    fn type_of() -> &'static str {
+        "GreetingError"
+    }
+}
+
+impl PrintProgramError for GreetingError {
+    fn print<E>(&self)
+    where
+        E: 'static
+            + std::error::Error
+            + DecodeError<E>
+            + PrintProgramError
+            + num_traits::FromPrimitive,
+    {
+        match self {
+            GreetingError::WrongOwner => msg!("Error: Account is not owned by this program"),
+            GreetingError::NotWritable => msg!("Error: Account is not writable"),
+            GreetingError::CounterOverflow => msg!("Error: Counter overflowed"),
+            GreetingError::NotRentExempt => msg!("Error: Account is not rent exempt"),
+        }
+    }
 }
 
 // Declare and export the program's entrypoint
@@ -26,14 +113,59 @@ pub fn process_instruction(
     //we will have mirror of these three parameters in client side Javascript code
     program_id: &Pubkey, // Public key of the account the hello world program was loaded into
     accounts: &[AccountInfo], // The account to say hello to (array of accounts that our specific program claims to need access to)
-    _instruction_data: &[u8], //Byte array data that acts as parameters for our program to make decisions on // Ignored, all helloworld instructions are hellos
+    instruction_data: &[u8], //Byte array data that acts as parameters for our program to make decisions on
 ) -> ProgramResult {
     //below is the call to do logging (solana logs -u localhost)
     msg!("Hello World Rust program entrypoint"); //msg! instead of printl (not performant)
 
+    // Decode which instruction the client asked for instead of hardwiring an increment
+    let instruction = GreetingInstruction::try_from_slice(instruction_data)?;
+
     // Iterating accounts is safer than indexing
     let accounts_iter = &mut accounts.iter();
 
+    // Initialize is the odd one out: it creates the account via a System Program CPI
+    // instead of mutating one that's already owned by this program, so handle it first.
+    if let GreetingInstruction::Initialize { seed } = instruction {
+        let payer = next_account_info(accounts_iter)?;
+        let pda_account = next_account_info(accounts_iter)?;
+        let system_program = next_account_info(accounts_iter)?;
+
+        let (pda, bump_seed) =
+            Pubkey::find_program_address(&[payer.key.as_ref(), seed.as_bytes()], program_id);
+        if pda != *pda_account.key {
+            msg!("Derived PDA does not match the provided account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let len = greeting_account_len(0);
+        let lamports = Rent::get()?.minimum_balance(len);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                pda_account.key,
+                lamports,
+                len as u64,
+                program_id,
+            ),
+            &[payer.clone(), pda_account.clone(), system_program.clone()],
+            &[&[payer.key.as_ref(), seed.as_bytes(), &[bump_seed]]],
+        )?;
+
+        let mut data = pda_account.data.borrow_mut();
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        GreetingAccount {
+            counter: 0,
+            message: String::new(),
+        }
+        .serialize(&mut &mut data[8..])?;
+
+        msg!("Initialized greeting account at {}", pda_account.key);
+
+        return Ok(());
+    }
+
     // Get the account to say hello to
     let account = next_account_info(accounts_iter)?; //helper function to grab next account
 
@@ -41,15 +173,77 @@ pub fn process_instruction(
     //owner doesn't mean user with private key, it means programmatic controller of the account
     if account.owner != program_id {
         msg!("Greeted account does not have the correct program id");
-        return Err(ProgramError::IncorrectProgramId);
+        return Err(GreetingError::WrongOwner.into());
+    }
+
+    // The following checks are redundant with guarantees the runtime already gives us,
+    // but making them explicit means a read-only, undersized, or non-rent-exempt account
+    // fails loudly here instead of silently corrupting data further down.
+    if !account.is_writable {
+        msg!("Greeted account is not writable");
+        return Err(GreetingError::NotWritable.into());
+    }
+
+    let expected_len = greeting_account_len(0);
+    if account.data_len() < expected_len {
+        msg!("Greeted account data is too small");
+        return Err(ProgramError::AccountDataTooSmall);
     }
 
-    // Increment and store the number of times the account has been greeted
+    if !Rent::get()?.is_exempt(**account.lamports.borrow(), account.data_len()) {
+        msg!("Greeted account is not rent exempt");
+        return Err(GreetingError::NotRentExempt.into());
+    }
 
     //we can put any type of data we want as long as we encode and decode it properly
-    let mut greeting_account = GreetingAccount::try_from_slice(&account.data.borrow())?; //decode data into actual type instance
-    greeting_account.counter += 1; //increments counter (useless, but shows mechanics)
-    greeting_account.serialize(&mut &mut account.data.borrow_mut()[..])?; //encoding back into data
+    let mut greeting_account = {
+        let data = account.data.borrow();
+        if data.len() < 8 || data[..8] != GREETING_ACCOUNT_DISCRIMINATOR {
+            msg!("Greeted account does not have the expected discriminator");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        GreetingAccount::try_from_slice(&data[8..])? //decode data into actual type instance
+    };
+
+    match instruction {
+        GreetingInstruction::Increment(amount) => {
+            greeting_account.counter = greeting_account
+                .counter
+                .checked_add(amount)
+                .ok_or(GreetingError::CounterOverflow)?;
+        }
+        GreetingInstruction::Reset => greeting_account.counter = 0,
+        GreetingInstruction::SetCounter(value) => greeting_account.counter = value,
+        GreetingInstruction::SetMessage(message) => {
+            let needed_len = greeting_account_len(message.len());
+            if account.data_len() < needed_len {
+                let payer = next_account_info(accounts_iter)?;
+                let system_program = next_account_info(accounts_iter)?;
+
+                let new_minimum = Rent::get()?.minimum_balance(needed_len);
+                let current_lamports = **account.lamports.borrow();
+                if new_minimum > current_lamports {
+                    invoke(
+                        &system_instruction::transfer(
+                            payer.key,
+                            account.key,
+                            new_minimum - current_lamports,
+                        ),
+                        &[payer.clone(), account.clone(), system_program.clone()],
+                    )?;
+                }
+
+                account.realloc(needed_len, false)?;
+            }
+
+            greeting_account.message = message;
+        }
+        GreetingInstruction::Initialize { .. } => unreachable!("Initialize returns above"),
+    }
+
+    let mut data = account.data.borrow_mut();
+    data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+    greeting_account.serialize(&mut &mut data[8..])?; //encoding back into data
 
     msg!("Greeted {} time(s)!", greeting_account.counter); //close off by logging we are done!
 
@@ -61,14 +255,44 @@ pub fn process_instruction(
 mod test {
     use super::*;
     use solana_program::clock::Epoch;
-    use std::mem;
+    use solana_program::entrypoint::SUCCESS;
+    use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+    use std::sync::Once;
+
+    fn rent_exempt_lamports(data_len: usize) -> u64 {
+        Rent::default().minimum_balance(data_len)
+    }
+
+    /// Plain `AccountInfo`-based unit tests run outside the BPF runtime, so `Rent::get()`
+    /// has no sysvar to read and returns `UnsupportedSysvar` by default. Installing this
+    /// stub once makes it return a default `Rent`, matching what `rent_exempt_lamports`
+    /// above already assumes.
+    struct TestSyscallStubs {}
+
+    impl SyscallStubs for TestSyscallStubs {
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Rent) = Rent::default();
+            }
+            SUCCESS
+        }
+    }
+
+    fn use_test_syscall_stubs() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            set_syscall_stubs(Box::new(TestSyscallStubs {}));
+        });
+    }
 
     #[test]
     fn test_sanity() {
+        use_test_syscall_stubs();
         let program_id = Pubkey::default();
         let key = Pubkey::default();
-        let mut lamports = 0;
-        let mut data = vec![0; mem::size_of::<u32>()];
+        let mut data = vec![0; greeting_account_len(0)];
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        let mut lamports = rent_exempt_lamports(data.len());
         let owner = Pubkey::default();
         let account = AccountInfo::new(
             &key,
@@ -80,29 +304,346 @@ mod test {
             false,
             Epoch::default(),
         );
-        let instruction_data: Vec<u8> = Vec::new();
+        let instruction_data = GreetingInstruction::Increment(1).try_to_vec().unwrap();
 
         let accounts = vec![account];
 
         assert_eq!(
-            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow()[8..])
                 .unwrap()
                 .counter,
             0
         );
         process_instruction(&program_id, &accounts, &instruction_data).unwrap();
         assert_eq!(
-            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow()[8..])
                 .unwrap()
                 .counter,
             1
         );
         process_instruction(&program_id, &accounts, &instruction_data).unwrap();
         assert_eq!(
-            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow()[8..])
                 .unwrap()
                 .counter,
             2
         );
     }
+
+    #[test]
+    fn test_rejects_wrong_owner() {
+        use_test_syscall_stubs();
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut data = vec![0; greeting_account_len(0)];
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        let mut lamports = rent_exempt_lamports(data.len());
+        let owner = Pubkey::new_unique(); // deliberately not program_id
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let instruction_data = GreetingInstruction::Increment(1).try_to_vec().unwrap();
+
+        let err = process_instruction(&program_id, &[account], &instruction_data).unwrap_err();
+        assert_eq!(err, GreetingError::WrongOwner.into());
+    }
+
+    #[test]
+    fn test_rejects_overflowing_counter() {
+        use_test_syscall_stubs();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut data = vec![0; greeting_account_len(0)];
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        GreetingAccount {
+            counter: u32::MAX,
+            message: String::new(),
+        }
+        .serialize(&mut &mut data[8..])
+        .unwrap();
+        let mut lamports = rent_exempt_lamports(data.len());
+        let owner = Pubkey::default();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let instruction_data = GreetingInstruction::Increment(1).try_to_vec().unwrap();
+
+        let err = process_instruction(&program_id, &[account], &instruction_data).unwrap_err();
+        assert_eq!(err, GreetingError::CounterOverflow.into());
+    }
+
+    #[test]
+    fn test_rejects_non_writable_account() {
+        use_test_syscall_stubs();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut data = vec![0; greeting_account_len(0)];
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        let mut lamports = rent_exempt_lamports(data.len());
+        let owner = Pubkey::default();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            false, // not writable
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let instruction_data = GreetingInstruction::Increment(1).try_to_vec().unwrap();
+
+        let err = process_instruction(&program_id, &[account], &instruction_data).unwrap_err();
+        assert_eq!(err, GreetingError::NotWritable.into());
+    }
+
+    #[test]
+    fn test_rejects_undersized_account() {
+        use_test_syscall_stubs();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut data = vec![0; 4]; // too small to hold the discriminator and counter
+        let mut lamports = rent_exempt_lamports(data.len());
+        let owner = Pubkey::default();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let instruction_data = GreetingInstruction::Increment(1).try_to_vec().unwrap();
+
+        let err = process_instruction(&program_id, &[account], &instruction_data).unwrap_err();
+        assert_eq!(err, ProgramError::AccountDataTooSmall);
+    }
+
+    #[test]
+    fn test_rejects_non_rent_exempt_account() {
+        use_test_syscall_stubs();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let mut data = vec![0; greeting_account_len(0)];
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        let mut lamports = 0; // far below the rent-exempt minimum for this data length
+        let owner = Pubkey::default();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let instruction_data = GreetingInstruction::Increment(1).try_to_vec().unwrap();
+
+        let err = process_instruction(&program_id, &[account], &instruction_data).unwrap_err();
+        assert_eq!(err, GreetingError::NotRentExempt.into());
+    }
+
+    #[test]
+    fn test_set_message_within_existing_capacity() {
+        use_test_syscall_stubs();
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        // Seed the account with a validly encoded prior message of the same
+        // serialized length as the new one, so the account already has exactly
+        // enough room and the growth path (payer, system program, realloc) isn't hit.
+        let initial_message = "aaaaa".to_string();
+        let message = "hello".to_string();
+        let len = greeting_account_len(message.len());
+        assert_eq!(greeting_account_len(initial_message.len()), len);
+
+        let mut data = vec![0; len];
+        data[..8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+        GreetingAccount {
+            counter: 0,
+            message: initial_message,
+        }
+        .serialize(&mut &mut data[8..])
+        .unwrap();
+        let mut lamports = rent_exempt_lamports(len);
+        let owner = Pubkey::default();
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let instruction_data = GreetingInstruction::SetMessage(message.clone())
+            .try_to_vec()
+            .unwrap();
+        let accounts = vec![account];
+
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
+
+        assert_eq!(
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow()[8..])
+                .unwrap()
+                .message,
+            message
+        );
+    }
+
+    #[test]
+    fn test_initialize_rejects_pda_mismatch() {
+        let program_id = Pubkey::new_unique();
+
+        let payer_key = Pubkey::new_unique();
+        let mut payer_lamports = 0;
+        let mut payer_data = vec![];
+        let payer = AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &program_id,
+            false,
+            Epoch::default(),
+        );
+
+        // Any account that isn't the actual PDA derived from `payer_key` and `seed`.
+        let wrong_pda_key = Pubkey::new_unique();
+        let mut pda_lamports = 0;
+        let mut pda_data = vec![];
+        let pda_account = AccountInfo::new(
+            &wrong_pda_key,
+            false,
+            true,
+            &mut pda_lamports,
+            &mut pda_data,
+            &program_id,
+            false,
+            Epoch::default(),
+        );
+
+        let system_program_id = solana_program::system_program::id();
+        let mut system_lamports = 0;
+        let mut system_data = vec![];
+        let system_program = AccountInfo::new(
+            &system_program_id,
+            false,
+            false,
+            &mut system_lamports,
+            &mut system_data,
+            &system_program_id,
+            true,
+            Epoch::default(),
+        );
+
+        let instruction_data = GreetingInstruction::Initialize {
+            seed: "greeting".to_string(),
+        }
+        .try_to_vec()
+        .unwrap();
+        let accounts = vec![payer, pda_account, system_program];
+
+        let err = process_instruction(&program_id, &accounts, &instruction_data).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidArgument);
+    }
+
+    #[test]
+    fn test_set_message_grows_account_when_needed() {
+        use_test_syscall_stubs();
+
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let message = "a message too long for the account's original allocation".to_string();
+        let old_len = greeting_account_len(0);
+        let needed_len = greeting_account_len(message.len());
+
+        // `AccountInfo::realloc` writes the new length 8 bytes before the data
+        // pointer and allows growing into up to `MAX_PERMITTED_DATA_INCREASE` of
+        // slack past the original length, mirroring the buffer layout the runtime
+        // itself serializes accounts into. Reproduce that shape here so the real
+        // realloc path runs instead of being skipped by the test.
+        let mut backing = vec![0u8; 8 + old_len + solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE];
+        backing[8..8 + 8].copy_from_slice(&GREETING_ACCOUNT_DISCRIMINATOR);
+
+        // Fund the account with enough lamports to already cover the post-growth
+        // rent-exempt minimum, so the handler takes the realloc path without also
+        // needing a lamport-transfer CPI.
+        let mut lamports = rent_exempt_lamports(needed_len);
+        let owner = program_id;
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut backing[8..8 + old_len],
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        // The handler pulls these two unconditionally once it decides to grow the
+        // account, even though the branch below never ends up needing a transfer.
+        let payer_key = Pubkey::new_unique();
+        let mut payer_lamports = 0;
+        let mut payer_data = vec![];
+        let payer = AccountInfo::new(
+            &payer_key,
+            true,
+            true,
+            &mut payer_lamports,
+            &mut payer_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+
+        let system_program_id = solana_program::system_program::id();
+        let mut system_lamports = 0;
+        let mut system_data = vec![];
+        let system_program = AccountInfo::new(
+            &system_program_id,
+            false,
+            false,
+            &mut system_lamports,
+            &mut system_data,
+            &system_program_id,
+            true,
+            Epoch::default(),
+        );
+
+        let instruction_data = GreetingInstruction::SetMessage(message.clone())
+            .try_to_vec()
+            .unwrap();
+        let accounts = vec![account, payer, system_program];
+
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
+
+        assert_eq!(accounts[0].data_len(), needed_len);
+        assert_eq!(
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow()[8..])
+                .unwrap()
+                .message,
+            message
+        );
+    }
 }